@@ -0,0 +1,62 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// A thread-local container of dynamically-named stats of type `TStatType`,
+/// e.g. a struct that wants a per-field `BoxLocalCounter` without knowing the
+/// field names ahead of time. Stats are created lazily, on first use of a
+/// given key, via the generator function passed to `new`, and then reused
+/// for subsequent calls with the same key.
+pub struct FieldStatThreadLocal<TStatType> {
+    generator: fn(&str) -> TStatType,
+    stats: RefCell<HashMap<String, TStatType>>,
+}
+
+impl<TStatType> FieldStatThreadLocal<TStatType> {
+    /// Creates a new, empty container. `generator` is called the first time
+    /// a given key is seen, to create the stat that will be reused for all
+    /// later calls with that key.
+    pub fn new(generator: fn(&str) -> TStatType) -> Self {
+        Self {
+            generator,
+            stats: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `f` with the stat for `key`, creating it first if necessary.
+    pub fn with_stat<R>(&self, key: &str, f: impl FnOnce(&TStatType) -> R) -> R {
+        let mut stats = self.stats.borrow_mut();
+        let stat = stats
+            .entry(key.to_string())
+            .or_insert_with(|| (self.generator)(key));
+        f(stat)
+    }
+}
+
+impl<TStatType: crate::Counter> FieldStatThreadLocal<TStatType> {
+    /// Increments the counter stored under `key` by `value`.
+    pub fn add_value(&self, key: &str, value: i64) {
+        self.with_stat(key, |stat| stat.increment_value(value));
+    }
+}
+
+impl<TStatType: crate::Timeseries> FieldStatThreadLocal<TStatType> {
+    /// Adds `value` to the timeseries stored under `key`.
+    pub fn add_timeseries_value(&self, key: &str, value: i64) {
+        self.with_stat(key, |stat| stat.add_value(value));
+    }
+}
+
+impl<TStatType: crate::Histogram> FieldStatThreadLocal<TStatType> {
+    /// Adds `value` to the histogram stored under `key`.
+    pub fn add_histogram_value(&self, key: &str, value: i64) {
+        self.with_stat(key, |stat| stat.add_value(value));
+    }
+}
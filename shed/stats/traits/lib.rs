@@ -0,0 +1,20 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+mod field_stat_types;
+mod log_bucket;
+mod log_histogram;
+mod multi_level_timeseries;
+mod stat_types;
+mod stats_manager;
+
+pub use field_stat_types::*;
+pub use log_histogram::*;
+pub use multi_level_timeseries::*;
+pub use stat_types::*;
+pub use stats_manager::*;
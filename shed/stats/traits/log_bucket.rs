@@ -0,0 +1,20 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+/// The logarithmic bucketing scheme shared by `LogHistogram` and
+/// `MultiLevelTimeseries`'s per-sub-bucket histograms: `compress` maps a
+/// value to the index of the bucket it falls into and `decompress` maps a
+/// bucket index back to the representative value of that bucket.
+pub(crate) fn compress(value: i64, precision: f64, num_buckets: usize) -> usize {
+    let index = ((value.max(0) as f64 + 1.0).ln() * precision).round() as usize;
+    index.min(num_buckets - 1)
+}
+
+pub(crate) fn decompress(index: usize, precision: f64) -> f64 {
+    (index as f64 / precision).exp() - 1.0
+}
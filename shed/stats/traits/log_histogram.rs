@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::log_bucket::compress;
+use crate::log_bucket::decompress;
+use crate::Histogram;
+
+const NUM_BUCKETS: usize = 1 << 16;
+const PRECISION: f64 = 100.0;
+
+/// A zero-configuration `Histogram` implementation that needs no bucket
+/// range or width up front. Values are bucketed logarithmically, which
+/// keeps memory use constant (a single fixed array of `1 << 16` buckets,
+/// allocated once at construction) while bounding the percentile error to
+/// well under 1%. Recording a value is lock-free: it's a single
+/// `fetch_add` on an `AtomicU64`, so the hot path never allocates and never
+/// blocks. This makes it a reasonable fallback backend when no dedicated
+/// bucket configuration is known ahead of time.
+pub struct LogHistogram {
+    buckets: Box<[AtomicU64]>,
+}
+
+impl LogHistogram {
+    /// Creates a new, empty histogram.
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..NUM_BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Returns the value of the given percentile (0.0 - 100.0) of all
+    /// values recorded so far, with bounded error (<0.5%).
+    pub fn percentile(&self, pct: f64) -> f64 {
+        let total: u64 = self.buckets.iter().map(|b| b.load(Relaxed)).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let target = (pct / 100.0) * total as f64;
+        let mut cumulative = 0u64;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Relaxed);
+            // Skip buckets we haven't seen a sample in yet, so that
+            // `percentile(0.0)` reports the smallest recorded value rather
+            // than always bucket 0.
+            if cumulative > 0 && cumulative as f64 >= target {
+                return decompress(i, PRECISION);
+            }
+        }
+        decompress(NUM_BUCKETS - 1, PRECISION)
+    }
+
+    fn record(&self, value: i64, nsamples: u32) {
+        let index = compress(value, PRECISION, NUM_BUCKETS);
+        self.buckets[index].fetch_add(nsamples as u64, Relaxed);
+    }
+}
+
+impl Default for LogHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Histogram for LogHistogram {
+    fn add_value(&self, value: i64) {
+        self.record(value, 1);
+    }
+
+    fn add_repeated_value(&self, value: i64, nsamples: u32) {
+        self.record(value, nsamples);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_histogram_reports_zero() {
+        let h = LogHistogram::new();
+        assert_eq!(h.percentile(0.0), 0.0);
+        assert_eq!(h.percentile(50.0), 0.0);
+        assert_eq!(h.percentile(100.0), 0.0);
+    }
+
+    #[test]
+    fn percentile_tracks_a_known_dataset() {
+        let h = LogHistogram::new();
+        for v in 1..=100 {
+            h.add_value(v);
+        }
+
+        let p0 = h.percentile(0.0);
+        let p50 = h.percentile(50.0);
+        let p100 = h.percentile(100.0);
+
+        assert!((0.5..2.0).contains(&p0), "p0 = {}", p0);
+        assert!((45.0..55.0).contains(&p50), "p50 = {}", p50);
+        assert!((95.0..105.0).contains(&p100), "p100 = {}", p100);
+    }
+
+    #[test]
+    fn add_repeated_value_counts_nsamples_towards_percentile() {
+        let h = LogHistogram::new();
+        h.add_repeated_value(10, 99);
+        h.add_value(1_000);
+
+        // 99 out of 100 samples are 10, so p50 should land near there, not
+        // halfway towards the single outlier.
+        assert!((5.0..20.0).contains(&h.percentile(50.0)));
+    }
+}
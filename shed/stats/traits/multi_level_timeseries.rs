@@ -0,0 +1,299 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::log_bucket::compress;
+use crate::log_bucket::decompress;
+use crate::Histogram;
+
+const SUBBUCKETS_PER_LEVEL: usize = 60;
+const QUANTILE_HIST_BUCKETS: usize = 2048;
+// Same precision as `LogHistogram` so that a sub-bucket can still represent
+// values up to ~1e9 (e.g. nanosecond-scale durations up to ~1s) despite its
+// much smaller bucket count.
+const QUANTILE_HIST_PRECISION: f64 = 100.0;
+
+/// A small logarithmically-bucketed histogram used to estimate percentiles
+/// within a single sub-bucket of a `Level`. It has the same bucket
+/// resolution as `LogHistogram` but far fewer buckets (and so a narrower
+/// value range), since a `MultiLevelTimeseries` keeps one of these per
+/// sub-bucket, per level.
+#[derive(Clone)]
+struct QuantileHistogram {
+    buckets: Vec<u32>,
+}
+
+impl QuantileHistogram {
+    fn new() -> Self {
+        Self {
+            buckets: vec![0; QUANTILE_HIST_BUCKETS],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(|b| *b = 0);
+    }
+
+    fn add(&mut self, value: i64, nsamples: u32) {
+        let index = compress(value, QUANTILE_HIST_PRECISION, QUANTILE_HIST_BUCKETS);
+        self.buckets[index] = self.buckets[index].saturating_add(nsamples);
+    }
+
+    fn merge_from(&mut self, other: &QuantileHistogram) {
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a = a.saturating_add(*b);
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.buckets.iter().map(|&c| c as u64).sum()
+    }
+
+    fn sum(&self) -> f64 {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| decompress(i, QUANTILE_HIST_PRECISION) * c as f64)
+            .sum()
+    }
+
+    fn percentile(&self, pct: f32) -> f64 {
+        let total = self.count();
+        if total == 0 {
+            return 0.0;
+        }
+        let target = (pct as f64 / 100.0) * total as f64;
+        let mut cumulative = 0u64;
+        for (i, &c) in self.buckets.iter().enumerate() {
+            cumulative += c as u64;
+            // Skip buckets we haven't seen a sample in yet, so that
+            // `percentile(0.0)` reports the smallest recorded value rather
+            // than always bucket 0.
+            if cumulative > 0 && cumulative as f64 >= target {
+                return decompress(i, QUANTILE_HIST_PRECISION);
+            }
+        }
+        decompress(QUANTILE_HIST_BUCKETS - 1, QUANTILE_HIST_PRECISION)
+    }
+}
+
+/// One sub-bucket (sliver of wall-clock time) of a `Level`. `wall_index` is
+/// the index of the wall-clock slice currently held here; when a slot is
+/// reused for a new, later slice its histogram is cleared first, so only
+/// that expired sliver is dropped rather than the whole window.
+struct SubBucket {
+    wall_index: Option<u64>,
+    histogram: QuantileHistogram,
+}
+
+impl SubBucket {
+    fn new() -> Self {
+        Self {
+            wall_index: None,
+            histogram: QuantileHistogram::new(),
+        }
+    }
+}
+
+/// A single ring buffer covering `window`, divided into `SUBBUCKETS_PER_LEVEL`
+/// fixed-size sub-buckets that are rotated in as wall-clock time advances.
+struct Level {
+    subbucket_duration: Duration,
+    subbuckets: Vec<Mutex<SubBucket>>,
+}
+
+impl Level {
+    fn new(window: Duration) -> Self {
+        let subbucket_duration = window / SUBBUCKETS_PER_LEVEL as u32;
+        Self {
+            subbucket_duration,
+            subbuckets: (0..SUBBUCKETS_PER_LEVEL)
+                .map(|_| Mutex::new(SubBucket::new()))
+                .collect(),
+        }
+    }
+
+    fn wall_index_at(&self, now: Instant, epoch: Instant) -> u64 {
+        let nanos_per_subbucket = self.subbucket_duration.as_nanos().max(1);
+        (now.duration_since(epoch).as_nanos() / nanos_per_subbucket) as u64
+    }
+
+    fn add_value(&self, value: i64, nsamples: u32, now: Instant, epoch: Instant) {
+        let wall_index = self.wall_index_at(now, epoch);
+        let slot = (wall_index as usize) % self.subbuckets.len();
+        let mut sub = self.subbuckets[slot]
+            .lock()
+            .expect("subbucket lock poisoned");
+        if sub.wall_index != Some(wall_index) {
+            sub.histogram.clear();
+            sub.wall_index = Some(wall_index);
+        }
+        sub.histogram.add(value, nsamples);
+    }
+
+    /// Merges together only the sub-buckets that still fall within the live
+    /// window, i.e. drops expired sub-buckets instead of the whole level.
+    fn live_histogram(&self, now: Instant, epoch: Instant) -> QuantileHistogram {
+        let current_wall_index = self.wall_index_at(now, epoch);
+        let mut merged = QuantileHistogram::new();
+        for sub in &self.subbuckets {
+            let sub = sub.lock().expect("subbucket lock poisoned");
+            if let Some(wall_index) = sub.wall_index {
+                if current_wall_index.saturating_sub(wall_index) < self.subbuckets.len() as u64 {
+                    merged.merge_from(&sub.histogram);
+                }
+            }
+        }
+        merged
+    }
+}
+
+/// The current aggregation of one level (one configured interval) of a
+/// `MultiLevelTimeseries`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelReport {
+    pub sum: f64,
+    pub count: u64,
+    pub avg: f64,
+    pub rate: f64,
+    pub percentiles: Vec<(f32, f64)>,
+}
+
+/// A `Histogram` backed by several concurrent ring buffers, one per
+/// configured interval (e.g. 60s, 600s, 3600s, or an effectively all-time
+/// window). `add_value`/`add_repeated_value` update every level once each;
+/// querying a level reports sum, count, avg, rate and the requested
+/// percentiles computed only over that level's still-live sub-buckets, so
+/// callers get true "last N minutes" percentiles instead of all-time
+/// aggregates that become meaningless on long-running services.
+pub struct MultiLevelTimeseries {
+    epoch: Instant,
+    percentiles: Vec<f32>,
+    levels: Vec<Level>,
+}
+
+impl MultiLevelTimeseries {
+    /// Creates a new timeseries with one level per entry in `intervals`,
+    /// each reporting the given `percentiles` (0.0 - 100.0) when queried.
+    pub fn new(percentiles: &[f32], intervals: &[Duration]) -> Self {
+        Self {
+            epoch: Instant::now(),
+            percentiles: percentiles.to_vec(),
+            levels: intervals.iter().copied().map(Level::new).collect(),
+        }
+    }
+
+    /// Like `add_value`, but takes the current time explicitly instead of
+    /// reading `Instant::now()`, so that sub-bucket rotation can be driven
+    /// deterministically (e.g. from a test).
+    pub fn add_value_at(&self, value: i64, now: Instant) {
+        self.add_repeated_value_at(value, 1, now);
+    }
+
+    /// Like `add_repeated_value`, but takes the current time explicitly
+    /// instead of reading `Instant::now()`, so that sub-bucket rotation can
+    /// be driven deterministically (e.g. from a test).
+    pub fn add_repeated_value_at(&self, value: i64, nsamples: u32, now: Instant) {
+        for level in &self.levels {
+            level.add_value(value, nsamples, now, self.epoch);
+        }
+    }
+
+    /// Returns the current aggregation of the level at `interval_index`
+    /// (the index into the `intervals` slice passed to `new`).
+    pub fn report(&self, interval_index: usize) -> LevelReport {
+        self.report_at(interval_index, Instant::now())
+    }
+
+    /// Like `report`, but takes the current time explicitly instead of
+    /// reading `Instant::now()`, so that sub-bucket expiry can be driven
+    /// deterministically (e.g. from a test).
+    pub fn report_at(&self, interval_index: usize, now: Instant) -> LevelReport {
+        let level = &self.levels[interval_index];
+        let histogram = level.live_histogram(now, self.epoch);
+        let count = histogram.count();
+        let sum = histogram.sum();
+        let avg = if count == 0 { 0.0 } else { sum / count as f64 };
+        let window_secs = (level.subbucket_duration * level.subbuckets.len() as u32).as_secs_f64();
+        let rate = if window_secs == 0.0 {
+            0.0
+        } else {
+            count as f64 / window_secs
+        };
+        let percentiles = self
+            .percentiles
+            .iter()
+            .map(|&pct| (pct, histogram.percentile(pct)))
+            .collect();
+        LevelReport {
+            sum,
+            count,
+            avg,
+            rate,
+            percentiles,
+        }
+    }
+}
+
+impl Histogram for MultiLevelTimeseries {
+    fn add_value(&self, value: i64) {
+        self.add_value_at(value, Instant::now());
+    }
+
+    fn add_repeated_value(&self, value: i64, nsamples: u32) {
+        self.add_repeated_value_at(value, nsamples, Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn value_stays_live_within_the_window() {
+        let ts = MultiLevelTimeseries::new(&[50.0], &[Duration::from_secs(60)]);
+        let t0 = ts.epoch;
+
+        ts.add_value_at(42, t0);
+
+        let report = ts.report_at(0, t0 + Duration::from_secs(30));
+        assert_eq!(report.count, 1);
+        assert_eq!(report.sum.round(), 42.0);
+    }
+
+    #[test]
+    fn value_drops_out_once_its_subbucket_rotates_out_of_the_window() {
+        let ts = MultiLevelTimeseries::new(&[50.0], &[Duration::from_secs(60)]);
+        let t0 = ts.epoch;
+
+        ts.add_value_at(42, t0);
+
+        // Advance well past the whole window: the sub-bucket that held our
+        // sample now lies older than the window, so it should no longer be
+        // counted as live even though its slot has not been reused.
+        let report = ts.report_at(0, t0 + Duration::from_secs(120));
+        assert_eq!(report.count, 0);
+    }
+
+    #[test]
+    fn add_value_updates_every_level_once() {
+        let ts = MultiLevelTimeseries::new(
+            &[50.0],
+            &[Duration::from_secs(60), Duration::from_secs(600)],
+        );
+        let t0 = ts.epoch;
+
+        ts.add_value_at(42, t0);
+
+        assert_eq!(ts.report_at(0, t0).count, 1);
+        assert_eq!(ts.report_at(1, t0).count, 1);
+    }
+}
@@ -11,6 +11,49 @@ use auto_impl::auto_impl;
 pub type BoxCounter = Box<dyn Counter + Send + Sync>;
 pub type BoxTimeseries = Box<dyn Timeseries + Send + Sync>;
 pub type BoxHistogram = Box<dyn Histogram + Send + Sync>;
+pub type BoxSingletonCounter = Box<dyn SingletonCounter + Send + Sync>;
+
+/// Like `BoxCounter`, but without the `Send + Sync` bound, for stats that
+/// are confined to a single thread (e.g. aggregated into a shared parent on
+/// drop) and so never need to cross a thread boundary themselves.
+pub type BoxLocalCounter = Box<dyn Counter>;
+/// Like `BoxTimeseries`, but without the `Send + Sync` bound.
+pub type BoxLocalTimeseries = Box<dyn Timeseries>;
+/// Like `BoxHistogram`, but without the `Send + Sync` bound.
+pub type BoxLocalHistogram = Box<dyn Histogram>;
+
+/// Names a single aggregation that a stat can report, so that a pull-based
+/// exporter can tell the different numbers reported for the same stat name
+/// apart (e.g. the sum vs. the rate of the same timeseries, or a particular
+/// percentile of a histogram).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ExportType {
+    /// The current value of a counter or singleton counter.
+    Value,
+    /// The sum of all values added during the interval.
+    Sum,
+    /// The average of all values added during the interval.
+    Avg,
+    /// The number of values added during the interval, divided by the
+    /// length of the interval in seconds.
+    Rate,
+    /// The number of values added during the interval.
+    Count,
+    /// The percentage of added values that were non-zero.
+    Percent,
+    /// The value of the given percentile (0-100) of a histogram.
+    Percentile(u8),
+}
+
+/// StatReader is implemented by stats that can report their own current
+/// aggregated values back out, e.g. so that a pull-based exporter can read
+/// them without going through `StatsManager::snapshot`.
+#[auto_impl(Box)]
+pub trait StatReader {
+    /// Returns the current value for the given export type, or `None` if
+    /// this stat does not report that aggregation.
+    fn get_value(&self, export_type: ExportType) -> Option<f64>;
+}
 
 /// Counter is the simples type of stat, it behaves as a single number that can
 /// be incremented.
@@ -18,6 +61,31 @@ pub type BoxHistogram = Box<dyn Histogram + Send + Sync>;
 pub trait Counter {
     /// Increments the counter by the given amount.
     fn increment_value(&self, value: i64);
+
+    /// Increments the counter by the given amount, attaching the given
+    /// key/value tags to the recorded sample. Implementations that cannot
+    /// express dimensions natively may fall back to concatenating the tags
+    /// into the stat name; the default simply ignores the tags.
+    fn increment_value_with_tags(&self, value: i64, tags: &[(&str, &str)]) {
+        let _ = tags;
+        self.increment_value(value);
+    }
+}
+
+/// SingletonCounter is a type of stat that, unlike Counter, is not monotonically
+/// incrementing. It behaves as a single value that can move up and down, and is
+/// reported as-is rather than aggregated, e.g. current connection count, queue
+/// depth or cache size.
+#[auto_impl(Box)]
+pub trait SingletonCounter {
+    /// Sets the value of the counter, overwriting whatever was set before.
+    fn set_value(&self, value: i64);
+
+    /// Increments the value of the counter by the given amount.
+    fn increment_value(&self, value: i64);
+
+    /// Decrements the value of the counter by the given amount.
+    fn decrement_value(&self, value: i64);
 }
 
 /// Timeseries is a type of stat that can aggregate data send to it into
@@ -33,6 +101,15 @@ pub trait Timeseries {
     /// Please notice that difference in the value semantic compared to
     /// `Histogram::add_repeated_value`.
     fn add_value_aggregated(&self, value: i64, nsamples: u32);
+
+    /// Adds value to the timeseries, attaching the given key/value tags to
+    /// the recorded sample. Implementations that cannot express dimensions
+    /// natively may fall back to concatenating the tags into the stat name;
+    /// the default simply ignores the tags.
+    fn add_value_with_tags(&self, value: i64, tags: &[(&str, &str)]) {
+        let _ = tags;
+        self.add_value(value);
+    }
 }
 
 /// Histogram is a type of stat that can aggregate data send to it into
@@ -50,6 +127,15 @@ pub trait Histogram {
     /// Please notice that difference in the value semantic compared to
     /// `Timeseries::add_value_aggregated`.
     fn add_repeated_value(&self, value: i64, nsamples: u32);
+
+    /// Adds value to the histogram, attaching the given key/value tags to
+    /// the recorded sample. Implementations that cannot express dimensions
+    /// natively may fall back to concatenating the tags into the stat name;
+    /// the default simply ignores the tags.
+    fn add_value_with_tags(&self, value: i64, tags: &[(&str, &str)]) {
+        let _ = tags;
+        self.add_value(value);
+    }
 }
 
 mod localkey_impls {
@@ -58,17 +144,23 @@ mod localkey_impls {
 
     pub trait CounterStatic {
         fn increment_value(&'static self, value: i64);
+        fn increment_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]);
     }
 
     impl<T: Counter> CounterStatic for LocalKey<T> {
         fn increment_value(&'static self, value: i64) {
             self.with(|s| T::increment_value(s, value));
         }
+
+        fn increment_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]) {
+            self.with(|s| T::increment_value_with_tags(s, value, tags));
+        }
     }
 
     pub trait TimeseriesStatic {
         fn add_value(&'static self, value: i64);
         fn add_value_aggregated(&'static self, value: i64, nsamples: u32);
+        fn add_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]);
     }
 
     impl<T: Timeseries> TimeseriesStatic for LocalKey<T> {
@@ -79,11 +171,36 @@ mod localkey_impls {
         fn add_value_aggregated(&'static self, value: i64, nsamples: u32) {
             self.with(|s| s.add_value_aggregated(value, nsamples));
         }
+
+        fn add_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]) {
+            self.with(|s| s.add_value_with_tags(value, tags));
+        }
+    }
+
+    pub trait SingletonCounterStatic {
+        fn set_value(&'static self, value: i64);
+        fn increment_value(&'static self, value: i64);
+        fn decrement_value(&'static self, value: i64);
+    }
+
+    impl<T: SingletonCounter> SingletonCounterStatic for LocalKey<T> {
+        fn set_value(&'static self, value: i64) {
+            self.with(|s| T::set_value(s, value));
+        }
+
+        fn increment_value(&'static self, value: i64) {
+            self.with(|s| T::increment_value(s, value));
+        }
+
+        fn decrement_value(&'static self, value: i64) {
+            self.with(|s| T::decrement_value(s, value));
+        }
     }
 
     pub trait HistogramStatic {
         fn add_value(&'static self, value: i64);
         fn add_repeated_value(&'static self, value: i64, nsamples: u32);
+        fn add_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]);
     }
 
     impl<T: Histogram> HistogramStatic for LocalKey<T> {
@@ -94,6 +211,10 @@ mod localkey_impls {
         fn add_repeated_value(&'static self, value: i64, nsamples: u32) {
             self.with(|s| s.add_repeated_value(value, nsamples));
         }
+
+        fn add_value_with_tags(&'static self, value: i64, tags: &[(&str, &str)]) {
+            self.with(|s| s.add_value_with_tags(value, tags));
+        }
     }
 }
-pub use localkey_impls::*;
\ No newline at end of file
+pub use localkey_impls::*;
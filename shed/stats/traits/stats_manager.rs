@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * This software may be used and distributed according to the terms of the
+ * GNU General Public License found in the LICENSE file in the root
+ * directory of this source tree.
+ */
+
+use std::time::Duration;
+
+use auto_impl::auto_impl;
+
+use crate::BoxHistogram;
+use crate::BoxLocalCounter;
+use crate::BoxLocalHistogram;
+use crate::BoxLocalTimeseries;
+use crate::ExportType;
+
+pub type BoxStatsManager = Box<dyn StatsManager + Send + Sync>;
+pub type BoxStatsManagerFactory = Box<dyn StatsManagerFactory + Send + Sync>;
+
+/// The type of aggregation to be performed on a Timeseries or Histogram over
+/// each of its configured intervals.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AggregationType {
+    /// The sum of all values added during the interval.
+    Sum,
+    /// The average of all values added during the interval.
+    Avg,
+    /// The number of values added during the interval, divided by the
+    /// length of the interval in seconds.
+    Rate,
+    /// The number of values added during the interval.
+    Count,
+    /// The percentage of added values that were non-zero.
+    Percent,
+}
+
+/// Configuration for the buckets of a Histogram: `width` is the size of each
+/// bucket and `min`/`max` bound the range of values that are bucketed, values
+/// outside of the range are clamped to the first or last bucket.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BucketConfig {
+    pub width: u32,
+    pub min: u32,
+    pub max: u32,
+}
+
+/// StatsManager owns a set of named stats, creating them on demand and
+/// periodically aggregating them. Unlike the leaf stat traits, which only
+/// know how to accept new samples, a StatsManager has a lifecycle: it is
+/// driven on a timer via `aggregate` so that the interval-based aggregations
+/// exposed by `Timeseries` and `Histogram` have somewhere to roll up into.
+#[auto_impl(Box)]
+pub trait StatsManager {
+    /// Rolls up all stats owned by this manager. Should be called
+    /// periodically, e.g. once a second, by the embedding application.
+    fn aggregate(&self);
+
+    /// Creates (or returns the already created) counter with the given name.
+    fn create_counter(&self, name: &str) -> BoxLocalCounter;
+
+    /// Creates (or returns the already created) timeseries with the given
+    /// name, aggregated using the given aggregation types over the given
+    /// intervals.
+    fn create_timeseries(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxLocalTimeseries;
+
+    /// Creates (or returns the already created) histogram with the given
+    /// name, bucketed according to `conf` and reporting the given
+    /// aggregation types and percentiles.
+    fn create_histogram(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+    ) -> BoxLocalHistogram;
+
+    /// Returns the current aggregated value of every stat owned by this
+    /// manager, as `(name, export_type, value)` triples. Intended to back a
+    /// pull-based exporter, e.g. a metrics scrape endpoint.
+    fn snapshot(&self) -> Vec<(String, ExportType, f64)>;
+
+    /// Creates (or returns the already created) sliding-window quantile stat
+    /// with the given name: a `Histogram` backed by one ring buffer per
+    /// entry in `intervals`, each reporting the given aggregation types and
+    /// percentiles over only its own still-live window (e.g. "last 60s" as
+    /// opposed to all-time). See `MultiLevelTimeseries` for a ready-made
+    /// implementation that backends can delegate to.
+    fn create_quantile_stat(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        percentiles: &[f32],
+        intervals: &[Duration],
+    ) -> BoxHistogram;
+}
+
+/// StatsManagerFactory creates new StatsManager instances, allowing the
+/// concrete backend (e.g. a no-op implementation, or one that reports to a
+/// real metrics pipeline) to be chosen independently of the code that uses
+/// it.
+#[auto_impl(Box)]
+pub trait StatsManagerFactory {
+    /// Creates a new StatsManager.
+    fn create(&self) -> BoxStatsManager;
+}